@@ -0,0 +1,58 @@
+use crate::span::Span;
+
+/// An error produced while scanning malformed input, carrying the [Span] of the offending text
+/// so a diagnostic can point precisely at it. The [super::Lexer] accumulates these instead of
+/// panicking or silently collapsing everything unrecognized into [super::TokenKind::Unknown], so
+/// the compiler can recover and keep tokenizing past the first mistake.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LexError {
+    /// A character that doesn't start any recognized token (operator, identifier, literal, or
+    /// custom rule).
+    UnexpectedCharacter(char, Span),
+
+    /// A `"..."` string literal that ran to EOF without a closing quote.
+    UnterminatedString(Span),
+
+    /// A `\u{...}` escape inside a string literal whose hex digits don't form a valid Unicode
+    /// scalar value, or whose closing `}` is missing or malformed (e.g. `\u{}`, `\u{41x`, or
+    /// `\u{d800}`, a surrogate code point with no corresponding `char`).
+    InvalidUnicodeEscape(Span),
+
+    /// A `#[ ... ]#` block comment that ran to EOF before its nesting depth returned to zero.
+    UnterminatedBlockComment(Span),
+
+    /// A raw string (`r"..."` / `r#"..."#`) that ran to EOF without its matching closing
+    /// delimiter.
+    UnterminatedRawString(Span),
+
+    /// A `\r` not immediately followed by `\n`, rather than silently dropping it or folding it
+    /// into whitespace.
+    DanglingCarriageReturn(Span),
+}
+
+impl LexError {
+    /// The [Span] of the offending text, for rendering a diagnostic caret under the source.
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedCharacter(_, span)
+            | LexError::UnterminatedString(span)
+            | LexError::InvalidUnicodeEscape(span)
+            | LexError::UnterminatedBlockComment(span)
+            | LexError::UnterminatedRawString(span)
+            | LexError::DanglingCarriageReturn(span) => *span,
+        }
+    }
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexError::UnexpectedCharacter(char, _) => write!(f, "unexpected character '{char}'"),
+            LexError::UnterminatedString(_) => write!(f, "unterminated string literal"),
+            LexError::InvalidUnicodeEscape(_) => write!(f, "invalid unicode escape"),
+            LexError::UnterminatedBlockComment(_) => write!(f, "unterminated block comment"),
+            LexError::UnterminatedRawString(_) => write!(f, "unterminated raw string literal"),
+            LexError::DanglingCarriageReturn(_) => write!(f, "'\\r' not followed by '\\n'"),
+        }
+    }
+}
@@ -4,11 +4,15 @@ use std::{
     str::Chars,
 };
 
+use crate::source_map::{self, BytePos};
 use crate::span::Span;
 
 use self::rules::LexerRule;
 mod rules;
 
+pub mod error;
+pub use error::LexError;
+
 #[cfg(test)]
 mod test;
 
@@ -20,9 +24,25 @@ pub struct Token {
 #[cfg_attr(test, derive(PartialEq, Debug))]
 pub enum TokenKind {
     Identifier(String),
+
+    /// An identifier written with the `r#` raw-identifier prefix (e.g. `r#for`), carrying only
+    /// the bare name with the prefix stripped. Kept as a distinct variant, rather than folded
+    /// into [TokenKind::Identifier], so later stages can tell it apart and skip keyword
+    /// classification even when the name collides with a reserved word.
+    RawIdentifier(String),
+
     IntegerLiteral(String),
+    FloatLiteral(String),
     StringLiteral(String),
 
+    /// A comment produced by one of the [rules::LEXER_RULES].
+    Comment,
+
+    /// A doc comment (`## ...`), produced by [rules::rule_line_comment]. Kept distinct from
+    /// [TokenKind::Comment] so later tooling (docs generation, hover info) can tell the two apart
+    /// without re-scanning the source text.
+    DocComment,
+
     /// \n
     NewLine,
 
@@ -168,15 +188,51 @@ use self::TokenKind::*;
 
 pub struct Cursor<'a> {
     source: Peekable<Enumerate<Chars<'a>>>,
+
+    /// The index of the most recently consumed character, used by [LexerRule]s to report an
+    /// accurate span end without threading it back through the main lexer loop. Updated by
+    /// [Self::next_char], and set to the triggering character before any rule runs.
+    last_idx: usize,
 }
 
 impl<'a> Cursor<'a> {
     pub fn new(source_code: &'a str) -> Cursor<'a> {
         Cursor {
             source: source_code.chars().enumerate().peekable(),
+            last_idx: 0,
         }
     }
 
+    /// Consumes and returns the next character, tracking its index in [Self::last_idx].
+    pub fn next_char(&mut self) -> Option<(usize, char)> {
+        let next = self.source.next();
+        if let Some((idx, _)) = next {
+            self.last_idx = idx;
+        }
+        next
+    }
+
+    /// Peeks at the next character without consuming it.
+    pub fn peek_char(&mut self) -> Option<(usize, char)> {
+        self.source.peek().copied()
+    }
+
+    /// Looks `n` characters ahead without consuming anything, by lexing from a clone of the
+    /// cursor's position. Used by rules like raw strings/identifiers that need to disambiguate a
+    /// multi-character prefix (e.g. how many `#`s) before committing to consume it.
+    pub fn peek_nth(&self, n: usize) -> Option<char> {
+        let mut iter = self.source.clone();
+        for _ in 0..n {
+            iter.next();
+        }
+        iter.peek().map(|(_, c)| *c)
+    }
+
+    /// The index of the most recently consumed character.
+    pub fn last_idx(&self) -> usize {
+        self.last_idx
+    }
+
     /// [Iterator::take_while] that does not consume non-matching items
     /// by peeking
     pub fn take_while(&mut self, predicate: fn(char) -> bool) -> Vec<char> {
@@ -189,7 +245,7 @@ impl<'a> Cursor<'a> {
             };
 
             if predicate(char.1) {
-                chars.push(self.source.next().unwrap().1);
+                chars.push(self.next_char().unwrap().1);
             } else {
                 return chars;
             }
@@ -232,10 +288,29 @@ pub struct Lexer<'a> {
 
     is_line_start: bool,
     line_start_idx: usize,
+
+    /// The absolute [BytePos] this lexer's input starts at in the thread-local
+    /// [source_map::SOURCE_MAP], so every emitted [Token]'s [Span] is already expressed in the
+    /// shared absolute coordinate system rather than being relative to `src` alone.
+    base: BytePos,
+
+    /// Diagnostics accumulated while scanning malformed input. Collected rather than returned
+    /// eagerly so a single bad token doesn't stop tokenizing; see [Self::errors].
+    errors: Vec<LexError>,
 }
 
 impl<'a> Lexer<'a> {
+    /// Builds a lexer over `source_code`, registering it into the thread-local
+    /// [source_map::SOURCE_MAP] so the resulting tokens' spans resolve back to a file, line/column
+    /// and snippet through [source_map::SourceMap::describe].
     pub fn new(source_code: &'a str) -> Lexer<'a> {
+        let base = source_map::register_anonymous_source(source_code);
+        Self::new_at(source_code, base)
+    }
+
+    /// Builds a lexer over `source_code` whose tokens' spans are offset by `base`, the start
+    /// [BytePos] of an already-registered [source_map::source_file::SourceFile].
+    pub fn new_at(source_code: &'a str, base: BytePos) -> Lexer<'a> {
         Lexer {
             cursor: Cursor::new(source_code),
             custom_rules: rules::LEXER_RULES,
@@ -244,8 +319,29 @@ impl<'a> Lexer<'a> {
             indentation_stack: vec![0],
             is_line_start: true,
             line_start_idx: 0,
+            base,
+            errors: Vec::new(),
         }
     }
+
+    /// Shifts a lexer-local `[start, end)` range by [Self::base] to produce a [Span] in the
+    /// shared absolute coordinate system.
+    fn shifted_span(&self, start: usize, end: usize) -> Span {
+        Span::new(start + self.base, end + self.base)
+    }
+
+    /// Records a [LexError] produced while scanning, including from within a [LexerRule]. Used
+    /// instead of panicking or silently discarding malformed input, so tokenizing can continue
+    /// and the compiler can surface every diagnostic at once.
+    pub(crate) fn push_error(&mut self, error: LexError) {
+        self.errors.push(error);
+    }
+
+    /// All [LexError]s accumulated so far. A [Lexer] is typically drained via [Iterator] before
+    /// this is read, since later tokens may still add to it.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
 }
 
 impl<'a> Iterator for Lexer<'a> {
@@ -264,7 +360,7 @@ impl<'a> Iterator for Lexer<'a> {
 
                 return Some(Token {
                     kind: Indent,
-                    span: Span::new(self.line_start_idx, self.line_start_idx),
+                    span: self.shifted_span(self.line_start_idx, self.line_start_idx),
                 });
             } else if indent_level < *self.indentation_stack.last()? {
                 let remaining_stack: Vec<usize> = self
@@ -284,7 +380,7 @@ impl<'a> Iterator for Lexer<'a> {
                 for _ in 0..popped_count {
                     self.token_queue.push_back(Token {
                         kind: Dedent,
-                        span: Span::new(self.line_start_idx, self.line_start_idx),
+                        span: self.shifted_span(self.line_start_idx, self.line_start_idx),
                     })
                 }
 
@@ -302,6 +398,24 @@ impl<'a> Iterator for Lexer<'a> {
         let start_idx = end_idx;
 
         let token_kind = match char {
+            // `r"..."` / `r#"..."#` raw strings must be tried before the generic identifier rule
+            // below, since `r` would otherwise always be lexed as the start of an identifier.
+            char if char == 'r' && matches!(self.cursor.peek_char(), Some((_, '"' | '#'))) => {
+                match apply_custom_rules(self, char, start_idx) {
+                    Some(kind) => {
+                        end_idx = self.cursor.last_idx();
+                        kind
+                    }
+                    None => {
+                        let mut chars: Vec<char> = self.cursor.take_while(is_ident_body);
+                        end_idx = end_idx + chars.len();
+
+                        chars.insert(0, char);
+
+                        Identifier(String::from_iter(&chars))
+                    }
+                }
+            }
             char if is_ident_start(char) => {
                 let mut chars: Vec<char> = self.cursor.take_while(is_ident_body);
                 end_idx = end_idx + chars.len();
@@ -312,13 +426,138 @@ impl<'a> Iterator for Lexer<'a> {
                 Identifier(ident_str)
             }
             char @ '0'..='9' => {
-                let mut chars: Vec<char> = self.cursor.take_while(is_digit);
-                end_idx = end_idx + chars.len();
+                let mut extra: Vec<char> = self.cursor.take_while(is_digit);
+                let mut is_float = false;
+
+                // A `.` only starts a fraction if it's followed by a digit, so `1.` isn't
+                // swallowed when it's actually an integer followed by `Dot` (e.g. `1.to_str()`).
+                if matches!(self.cursor.source.peek(), Some((_, '.')))
+                    && matches!(self.cursor.peek_nth(1), Some(c) if is_digit(c))
+                {
+                    is_float = true;
+                    extra.push(self.cursor.source.next()?.1);
+                    extra.extend(self.cursor.take_while(is_digit));
+                }
 
-                chars.insert(0, char);
+                let has_exponent = match self.cursor.peek_nth(0) {
+                    Some('e' | 'E') => {
+                        let digit_offset =
+                            if matches!(self.cursor.peek_nth(1), Some('+' | '-')) { 2 } else { 1 };
+                        matches!(self.cursor.peek_nth(digit_offset), Some(c) if is_digit(c))
+                    }
+                    _ => false,
+                };
+
+                if has_exponent {
+                    is_float = true;
+                    extra.push(self.cursor.source.next()?.1); // `e` or `E`
+                    if matches!(self.cursor.source.peek(), Some((_, '+' | '-'))) {
+                        extra.push(self.cursor.source.next()?.1);
+                    }
+                    extra.extend(self.cursor.take_while(is_digit));
+                }
+
+                end_idx = end_idx + extra.len();
+                extra.insert(0, char);
 
-                let int_str = String::from_iter(&chars);
-                IntegerLiteral(int_str)
+                let num_str = String::from_iter(&extra);
+                if is_float {
+                    FloatLiteral(num_str)
+                } else {
+                    IntegerLiteral(num_str)
+                }
+            }
+            '"' => {
+                let mut content = String::new();
+                loop {
+                    match self.cursor.source.next() {
+                        None => {
+                            let span = self.shifted_span(start_idx, end_idx + 1);
+                            self.push_error(LexError::UnterminatedString(span));
+                            break;
+                        }
+                        Some((idx, '"')) => {
+                            end_idx = idx;
+                            break;
+                        }
+                        Some((backslash_idx, '\\')) => match self.cursor.source.next() {
+                            Some((idx, 'n')) => {
+                                content.push('\n');
+                                end_idx = idx;
+                            }
+                            Some((idx, 't')) => {
+                                content.push('\t');
+                                end_idx = idx;
+                            }
+                            Some((idx, '\\')) => {
+                                content.push('\\');
+                                end_idx = idx;
+                            }
+                            Some((idx, '"')) => {
+                                content.push('"');
+                                end_idx = idx;
+                            }
+                            Some((idx, 'u')) if matches!(self.cursor.source.peek(), Some((_, '{'))) => {
+                                self.cursor.source.next();
+                                end_idx = idx;
+
+                                let mut hex = Vec::new();
+                                while let Some((_, c)) = self.cursor.source.peek() {
+                                    if !c.is_ascii_hexdigit() {
+                                        break;
+                                    }
+                                    let (hex_idx, hex_char) = self.cursor.source.next().unwrap();
+                                    end_idx = hex_idx;
+                                    hex.push(hex_char);
+                                }
+
+                                match self.cursor.source.next() {
+                                    Some((close_idx, '}')) => {
+                                        end_idx = close_idx;
+                                        let unicode_char = u32::from_str_radix(&String::from_iter(&hex), 16)
+                                            .ok()
+                                            .and_then(char::from_u32);
+                                        match unicode_char {
+                                            Some(unicode_char) => content.push(unicode_char),
+                                            None => {
+                                                let span = self.shifted_span(backslash_idx, end_idx + 1);
+                                                self.push_error(LexError::InvalidUnicodeEscape(span));
+                                            }
+                                        }
+                                    }
+                                    Some((bad_idx, _)) => {
+                                        end_idx = bad_idx;
+                                        let span = self.shifted_span(backslash_idx, end_idx + 1);
+                                        self.push_error(LexError::InvalidUnicodeEscape(span));
+                                    }
+                                    None => {
+                                        let span = self.shifted_span(start_idx, end_idx + 1);
+                                        self.push_error(LexError::UnterminatedString(span));
+                                        break;
+                                    }
+                                }
+                            }
+                            // An unrecognised escape is kept verbatim rather than treated as an
+                            // error, so e.g. `\q` survives as `q`.
+                            Some((idx, other)) => {
+                                content.push(other);
+                                end_idx = idx;
+                            }
+                            None => {
+                                end_idx = backslash_idx;
+                                let span = self.shifted_span(start_idx, end_idx + 1);
+                                self.push_error(LexError::UnterminatedString(span));
+                                break;
+                            }
+                        },
+                        Some((idx, c)) => {
+                            content.push(c);
+                            end_idx = idx;
+                        }
+                    }
+                }
+
+                StringLiteral(content)
             }
             '+' => match self.cursor.source.peek() {
                 Some((_, '=')) => {
@@ -458,21 +697,47 @@ impl<'a> Iterator for Lexer<'a> {
                 self.line_start_idx = start_idx + 1;
                 NewLine
             }
-            _ => Unknown,
+            '\r' => match self.cursor.source.peek() {
+                // `\r\n` is folded into a single `NewLine`, the same as a bare `\n`.
+                Some((_, '\n')) => {
+                    (end_idx, _) = self.cursor.source.next()?;
+                    self.is_line_start = true;
+                    self.line_start_idx = end_idx + 1;
+                    NewLine
+                }
+                _ => {
+                    let span = self.shifted_span(start_idx, start_idx + 1);
+                    self.push_error(LexError::DanglingCarriageReturn(span));
+                    Unknown
+                }
+            },
+            // Characters not handled by the punctuation/identifier logic above are offered to
+            // each custom lexer rule in turn; the first one to match wins.
+            char => match apply_custom_rules(self, char, start_idx) {
+                Some(kind) => {
+                    end_idx = self.cursor.last_idx();
+                    kind
+                }
+                None => {
+                    let span = self.shifted_span(start_idx, start_idx + 1);
+                    self.push_error(LexError::UnexpectedCharacter(char, span));
+                    Unknown
+                }
+            },
         };
 
-        let span = Span::new(start_idx, end_idx + 1);
+        let span = self.shifted_span(start_idx, end_idx + 1);
 
         if self.cursor.source.peek().is_none() {
             self.token_queue.push_back(Token {
                 kind: NewLine,
-                span: Span::new(end_idx + 1, end_idx + 2),
+                span: self.shifted_span(end_idx + 1, end_idx + 2),
             });
 
             for _ in 0..self.indentation_stack.len() - 1 {
                 self.token_queue.push_back(Token {
                     kind: Dedent,
-                    span: Span::new(end_idx + 2, end_idx + 2),
+                    span: self.shifted_span(end_idx + 2, end_idx + 2),
                 })
             }
         }
@@ -484,6 +749,22 @@ impl<'a> Iterator for Lexer<'a> {
     }
 }
 
+/// Offers `char` (the character that was just consumed, either one that didn't match any built-in
+/// token or an `r` that may start a raw string/identifier) to each rule in [Lexer::custom_rules]
+/// in order, returning the first [Some] produced.
+fn apply_custom_rules(lexer: &mut Lexer, char: char, start_idx: usize) -> Option<TokenKind> {
+    // The main loop consumed `char` without going through [Cursor::next_char], so sync
+    // [Cursor::last_idx] to it; a rule that consumes nothing further then still ends its span here.
+    lexer.cursor.last_idx = start_idx;
+    let rules = lexer.custom_rules;
+    for rule in rules {
+        if let Some(kind) = rule(lexer, char, start_idx) {
+            return Some(kind);
+        }
+    }
+    None
+}
+
 fn is_ident_start(c: char) -> bool {
     c == '_' || c.is_ascii_alphabetic()
 }
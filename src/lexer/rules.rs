@@ -1,9 +1,162 @@
-use crate::lexer::{Lexer, TokenKind};
+use crate::lexer::{error::LexError, is_ident_body, is_ident_start, Lexer, TokenKind};
 
-pub type LexerRule = fn(&mut Lexer, char) -> Option<TokenKind>;
+/// `start_idx` is the lexer-local index of `char`, the already-consumed character the rule is
+/// being offered, so a rule that fails partway through (e.g. hits EOF) can report a [LexError]
+/// spanning from where its construct opened rather than just the character that triggered it.
+pub type LexerRule = fn(&mut Lexer, char, usize) -> Option<TokenKind>;
 
-pub static LEXER_RULES: &[LexerRule] = &[rule_string_ident];
+pub static LEXER_RULES: &[LexerRule] = &[
+    rule_block_comment,
+    rule_line_comment,
+    rule_raw_ident,
+    rule_raw_string,
+];
 
-fn rule_string_ident(lexer: &mut Lexer, char: char) -> Option<TokenKind> {
-    None
+/// Counts the run of `#` characters immediately following the already-consumed `r`, without
+/// consuming any of them, so callers can decide whether they're looking at a raw string
+/// (`r#"..."#`) or a raw identifier (`r#name`) before committing to consume anything.
+fn count_leading_hashes(lexer: &Lexer) -> usize {
+    let mut n = 0;
+    while lexer.cursor.peek_nth(n) == Some('#') {
+        n += 1;
+    }
+    n
+}
+
+/// A nested block comment, written `#[ ... ]#`, the way `/* /* */ */` nests in languages with
+/// block comments. `#[` increases the nesting depth and `]#` decreases it; the comment ends only
+/// once the depth returns to zero.
+fn rule_block_comment(lexer: &mut Lexer, char: char, start_idx: usize) -> Option<TokenKind> {
+    if char != '#' {
+        return None;
+    }
+
+    match lexer.cursor.peek_char() {
+        Some((_, '[')) => {
+            lexer.cursor.next_char();
+        }
+        _ => return None,
+    }
+
+    let mut depth = 1usize;
+    loop {
+        match lexer.cursor.next_char() {
+            None => {
+                let span = lexer.shifted_span(start_idx, lexer.cursor.last_idx() + 1);
+                lexer.push_error(LexError::UnterminatedBlockComment(span));
+                return Some(TokenKind::Comment);
+            }
+            Some((_, '#')) if matches!(lexer.cursor.peek_char(), Some((_, '['))) => {
+                lexer.cursor.next_char();
+                depth += 1;
+            }
+            Some((_, ']')) if matches!(lexer.cursor.peek_char(), Some((_, '#'))) => {
+                lexer.cursor.next_char();
+                depth -= 1;
+                if depth == 0 {
+                    return Some(TokenKind::Comment);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A line comment, `# ...`, running to the next `\n` or EOF. A comment whose text begins with a
+/// second `#` (`## ...`) is treated as a doc comment, the way `///` is in Rust, and surfaces as
+/// [TokenKind::DocComment] instead of [TokenKind::Comment].
+fn rule_line_comment(lexer: &mut Lexer, char: char, _start_idx: usize) -> Option<TokenKind> {
+    if char != '#' {
+        return None;
+    }
+
+    // `#[` starts a nested block comment instead; leave it for [rule_block_comment].
+    if matches!(lexer.cursor.peek_char(), Some((_, '['))) {
+        return None;
+    }
+
+    let is_doc = matches!(lexer.cursor.peek_char(), Some((_, '#')));
+    if is_doc {
+        lexer.cursor.next_char();
+    }
+
+    lexer.cursor.take_while(|c| c != '\n');
+
+    Some(if is_doc {
+        TokenKind::DocComment
+    } else {
+        TokenKind::Comment
+    })
+}
+
+/// A raw string literal, `r"..."` or `r#"..."#`, where N leading `#`s must be matched by a
+/// closing `"` followed by exactly N `#`s, with no escape processing inside. Matches Rust's raw
+/// string syntax.
+fn rule_raw_string(lexer: &mut Lexer, char: char, start_idx: usize) -> Option<TokenKind> {
+    if char != 'r' {
+        return None;
+    }
+
+    let hashes = count_leading_hashes(lexer);
+    if lexer.cursor.peek_nth(hashes) != Some('"') {
+        // Not actually a raw string (e.g. `r#name`, a raw identifier); leave the cursor
+        // untouched so other rules, or the plain identifier fallback, can handle it instead.
+        return None;
+    }
+
+    for _ in 0..hashes {
+        lexer.cursor.next_char();
+    }
+    lexer.cursor.next_char();
+
+    let mut content = String::new();
+    loop {
+        match lexer.cursor.next_char() {
+            None => {
+                let span = lexer.shifted_span(start_idx, lexer.cursor.last_idx() + 1);
+                lexer.push_error(LexError::UnterminatedRawString(span));
+                break;
+            }
+            Some((_, '"')) => {
+                let mut closing_hashes = 0usize;
+                while closing_hashes < hashes && matches!(lexer.cursor.peek_char(), Some((_, '#'))) {
+                    lexer.cursor.next_char();
+                    closing_hashes += 1;
+                }
+
+                if closing_hashes == hashes {
+                    break;
+                }
+
+                content.push('"');
+                content.push_str(&"#".repeat(closing_hashes));
+            }
+            Some((_, c)) => content.push(c),
+        }
+    }
+
+    Some(TokenKind::StringLiteral(content))
+}
+
+/// A raw identifier, `r#name`, where the `r#` prefix is stripped and `name` is lexed as an
+/// ordinary [TokenKind::RawIdentifier] even if it would otherwise be a reserved word. Matches
+/// Rust's `r#` escape.
+fn rule_raw_ident(lexer: &mut Lexer, char: char, _start_idx: usize) -> Option<TokenKind> {
+    if char != 'r' || lexer.cursor.peek_nth(0) != Some('#') {
+        return None;
+    }
+
+    let Some(name_start) = lexer.cursor.peek_nth(1) else {
+        return None;
+    };
+    if !is_ident_start(name_start) {
+        return None;
+    }
+
+    lexer.cursor.next_char(); // consume `#`
+
+    let mut chars = vec![lexer.cursor.next_char()?.1];
+    chars.extend(lexer.cursor.take_while(is_ident_body));
+
+    Some(TokenKind::RawIdentifier(String::from_iter(&chars)))
 }
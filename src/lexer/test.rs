@@ -259,7 +259,37 @@ print(one)"#;
             tk::Identifier("__valid".into()),
             tk::Identifier("v4l1d".into()),
             tk::Identifier("valid_als0".into()),
-            tk::Unknown,
+            // A leading digit can never start an identifier, so `1nvalid` lexes as the digit run
+            // `1` followed by the identifier `nvalid` rather than one token.
+            tk::IntegerLiteral("1".into()),
+            tk::Identifier("nvalid".into()),
+            tk::NewLine,
+        ];
+
+        compare_tokens(kinds, src);
+    }
+
+    #[test]
+    fn raw_identifiers() {
+        let src = r#"r#for r#true"#;
+
+        let kinds = vec![
+            tk::RawIdentifier("for".into()),
+            tk::RawIdentifier("true".into()),
+            tk::NewLine,
+        ];
+
+        compare_tokens(kinds, src);
+    }
+
+    #[test]
+    fn raw_strings() {
+        let src = r####"r"hello" r#"a"b"#"####;
+
+        let kinds = vec![
+            tk::StringLiteral("hello".into()),
+            tk::StringLiteral("a\"b".into()),
+            tk::NewLine,
         ];
 
         compare_tokens(kinds, src);
@@ -281,6 +311,198 @@ print(one)"#;
         compare_tokens(kinds, src);
     }
 
+    #[test]
+    fn float_literals() {
+        let src = r#"1.5 0.25 1e10 1e+10 1.5e-3 1."#;
+
+        let kinds = vec![
+            tk::FloatLiteral("1.5".into()),
+            tk::FloatLiteral("0.25".into()),
+            tk::FloatLiteral("1e10".into()),
+            tk::FloatLiteral("1e+10".into()),
+            tk::FloatLiteral("1.5e-3".into()),
+            tk::IntegerLiteral("1".into()),
+            tk::Dot,
+            tk::NewLine,
+        ];
+
+        compare_tokens(kinds, src);
+    }
+
+    #[test]
+    fn string_escapes() {
+        let src = r#""line\nbreak\ttab\\slash\"quote\u{48}""#;
+
+        let kinds = vec![
+            tk::StringLiteral("line\nbreak\ttab\\slash\"quote\u{48}".into()),
+            tk::NewLine,
+        ];
+
+        compare_tokens(kinds, src);
+    }
+
+    #[test]
+    fn invalid_unicode_escape_bad_hex_terminator_reports_error() {
+        // `x` isn't a hex digit and isn't `}` either, so the escape never closes.
+        let src = r#""\u{41x""#;
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::InvalidUnicodeEscape(_)
+        ));
+    }
+
+    #[test]
+    fn invalid_unicode_escape_empty_braces_reports_error() {
+        let src = r#""\u{}""#;
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::InvalidUnicodeEscape(_)
+        ));
+    }
+
+    #[test]
+    fn invalid_unicode_escape_out_of_range_code_point_reports_error() {
+        // `d800` is a surrogate half; it's valid hex but not a valid `char`.
+        let src = r#""\u{d800}""#;
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::InvalidUnicodeEscape(_)
+        ));
+    }
+
+    #[test]
+    fn unicode_escape_eof_mid_escape_reports_unterminated_string() {
+        let src = "\"\\u{41";
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::UnterminatedString(_)
+        ));
+        assert_eq!(lexer.errors()[0].span().len, src.len());
+    }
+
+    #[test]
+    fn doc_comment() {
+        let src = r#"let i = true ## This documents i"#;
+
+        let kinds = vec![
+            tk::Identifier("let".into()),
+            tk::Identifier("i".into()),
+            tk::Equals,
+            tk::Identifier("true".into()),
+            tk::DocComment,
+            tk::NewLine,
+        ];
+
+        compare_tokens(kinds, src);
+    }
+
+    #[test]
+    fn nested_block_comment() {
+        let src = r#"1 #[ outer #[ inner ]# still outer ]# 2"#;
+
+        let kinds = vec![
+            tk::IntegerLiteral("1".into()),
+            tk::Comment,
+            tk::IntegerLiteral("2".into()),
+            tk::NewLine,
+        ];
+
+        compare_tokens(kinds, src);
+    }
+
+    #[test]
+    fn unterminated_string_reports_error() {
+        let src = "\"never closed";
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::UnterminatedString(_)
+        ));
+    }
+
+    #[test]
+    fn unterminated_string_span_includes_trailing_backslash() {
+        // 5 bytes: opening quote, "abc", and the dangling backslash that ends the input.
+        let src = r#""abc\"#;
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert_eq!(lexer.errors()[0].span().len, src.len());
+    }
+
+    #[test]
+    fn unterminated_block_comment_reports_error() {
+        let src = "#[ outer #[ inner ]# still outer";
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::UnterminatedBlockComment(_)
+        ));
+    }
+
+    #[test]
+    fn unterminated_raw_string_reports_error() {
+        let src = r####"r#"hello"####;
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::UnterminatedRawString(_)
+        ));
+    }
+
+    #[test]
+    fn dangling_carriage_return_reports_error() {
+        let src = "a\rb";
+        let mut lexer = Lexer::new(src);
+        let _: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::DanglingCarriageReturn(_)
+        ));
+    }
+
+    #[test]
+    fn unknown_character_reports_error() {
+        let src = "$";
+        let mut lexer = Lexer::new(src);
+        let toks: Vec<Token> = (&mut lexer).collect();
+
+        assert_eq!(toks[0].kind, tk::Unknown);
+        assert_eq!(lexer.errors().len(), 1);
+        assert!(matches!(
+            lexer.errors()[0],
+            crate::lexer::LexError::UnexpectedCharacter('$', _)
+        ));
+    }
+
     #[test]
     fn tok_lens() {
         let src = r#"let i = true"#;
@@ -6,7 +6,9 @@ use lexer::Lexer;
 use crate::lexer::Token;
 
 mod lexer;
+mod source_map;
 mod span;
+mod tree;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut buffer = String::new();
@@ -1,6 +1,28 @@
-use std::{rc::Rc, path::Path};
+use std::{rc::Rc, path::Path, sync::Arc};
 
 use crate::source_map::module_path;
+use crate::source_map::file_resolver::FileResolver;
+use crate::source_map::{source_file::SourceFile, BytePos};
+
+///The error produced when resolving a [DeferredSourceFile] fails. Carries the full module path
+///(via [module_path::ModulePath::to_string]) so callers can render it in a diagnostic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolveError{
+    ///A non-optional module whose backing path does not exist at all.
+    NotFound(String),
+    ///A module whose backing path exists but whose contents couldn't be read, carrying the
+    ///[super::file_resolver::FileResolver::read_file] failure message.
+    ReadFailed(String, String),
+}
+
+impl std::fmt::Display for ResolveError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self{
+            ResolveError::NotFound(path) => write!(f, "module \"{0}\" does not exist", path),
+            ResolveError::ReadFailed(path, reason) => write!(f, "module \"{0}\" could not be read: {1}", path, reason),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 ///A deferred source file, which simply represents a source file to be loaded later when needed
@@ -16,11 +38,34 @@ pub struct DeferredSourceFile{
 
 impl DeferredSourceFile{
     ///The constructor for this struct
-    pub fn new(path: module_path::ModulePath) -> Self{
+    pub fn new(path: module_path::ModulePath, resolver: Arc<dyn FileResolver>) -> Self{
         Self{
-            path, source_code: DeferredSourceCode{}
+            path, source_code: DeferredSourceCode{ resolver }
         }
     }
+
+    ///Resolves this deferred file into a loaded [SourceFile], assigning it `offset` as its start
+    ///[BytePos]. Borrows the "optional module" idea from the `just` grammar's `import ?`/`mod ?`:
+    ///when `optional` is true and the backing path does not exist, this returns `Ok(None)`
+    ///instead of failing, so a from-import can depend on a module that may legitimately be
+    ///absent (e.g. platform-specific or generated files) without aborting the whole compile. A
+    ///missing non-optional module still errors, carrying the full path for diagnostics.
+    pub fn resolve(&self, offset: BytePos, optional: bool) -> std::result::Result<Option<SourceFile>, ResolveError>{
+        let path_str = self.path.to_string();
+        let raw_path = Path::new(path_str.as_str());
+
+        if !self.source_code.exists(raw_path){
+            return if optional{
+                Ok(None)
+            }else{
+                Err(ResolveError::NotFound(self.path.to_string()))
+            };
+        }
+
+        SourceFile::new(self, offset)
+            .map(Some)
+            .map_err(|reason| ResolveError::ReadFailed(self.path.to_string(), reason))
+    }
 }
 
 impl std::fmt::Display for DeferredSourceFile{
@@ -29,30 +74,90 @@ impl std::fmt::Display for DeferredSourceFile{
     }
 }
 
-///An empty struct which simply acts as a means for interfacing with and representing source code
-///which has yet to be loaded
+///A means for interfacing with and representing source code which has yet to be loaded, via a
+///[FileResolver] rather than [std::fs] directly, so it can be backed by an in-memory mock in
+///tests.
 ///
 ///This is needed because we don't want to load source code right away in a project that may have
 ///files that are not yet or no longer being used. This is also good for ensuring that we only load
 ///the source code that we need at the moment
 #[derive(Clone, Debug)]
-pub struct DeferredSourceCode;
+pub struct DeferredSourceCode{
+    resolver: Arc<dyn FileResolver>
+}
 
 impl DeferredSourceCode{
     ///Checks whether a given path exists
     pub fn exists(&self, path: impl AsRef<Path>) -> bool{
-        path.as_ref().exists()
+        self.resolver.exists(path.as_ref())
     }
 
     ///Checks whether a given path is a file
     pub fn is_file(&self, path: impl AsRef<Path>) -> bool{
-        path.as_ref().is_file()
+        self.resolver.exists(path.as_ref()) && !self.resolver.is_dir(path.as_ref())
     }
 
     ///Attempts to load the source code into an `Rc<str>` and return it to be passed to a
-    ///[super::source_file::SourceFile] object
-    pub fn load(&self, path: impl AsRef<Path>) -> Rc<str>{
-        let data = std::fs::read_to_string(path).expect("Unable to read contents of file");
-        data.into()
+    ///[super::source_file::SourceFile] object, propagating the [FileResolver::read_file] error
+    ///message rather than panicking on an unreadable file (e.g. a TOCTOU race where the path is
+    ///removed between [Self::exists] and this call).
+    pub fn load(&self, path: impl AsRef<Path>) -> std::result::Result<Rc<str>, String>{
+        self.resolver.read_file(path.as_ref()).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::source_map::file_resolver::MockResolver;
+
+    #[test]
+    fn optional_resolve_of_missing_file_returns_none() {
+        let resolver: Arc<dyn FileResolver> = Arc::new(MockResolver::new());
+        let deferred = DeferredSourceFile::new(module_path::ModulePath::new("missing.sil", None), resolver);
+
+        let file = deferred.resolve(0, true).expect("optional resolve should not error");
+        assert!(file.is_none());
+    }
+
+    #[test]
+    fn non_optional_resolve_of_missing_file_errors() {
+        let resolver: Arc<dyn FileResolver> = Arc::new(MockResolver::new());
+        let deferred = DeferredSourceFile::new(module_path::ModulePath::new("missing.sil", None), resolver);
+
+        let err = deferred.resolve(0, false).expect_err("non-optional resolve should error");
+        assert_eq!(err, ResolveError::NotFound("missing.sil".into()));
+    }
+
+    /// A [FileResolver] whose backing path always exists and is never a directory, but whose
+    /// reads always fail, simulating e.g. a permissions error or a TOCTOU race where the file is
+    /// removed between [DeferredSourceCode::exists] and [DeferredSourceCode::load].
+    struct UnreadableResolver;
+
+    impl FileResolver for UnreadableResolver{
+        fn read_dir(&self, _path: &Path) -> Vec<std::path::PathBuf>{
+            Vec::new()
+        }
+
+        fn exists(&self, _path: &Path) -> bool{
+            true
+        }
+
+        fn is_dir(&self, _path: &Path) -> bool{
+            false
+        }
+
+        fn read_file(&self, _path: &Path) -> std::result::Result<String, String>{
+            Err("permission denied".into())
+        }
+    }
+
+    #[test]
+    fn resolve_of_unreadable_file_propagates_read_error() {
+        let resolver: Arc<dyn FileResolver> = Arc::new(UnreadableResolver);
+        let deferred = DeferredSourceFile::new(module_path::ModulePath::new("present.sil", None), resolver);
+
+        let err = deferred.resolve(0, false).expect_err("an unreadable file should surface its read error");
+        assert!(matches!(err, ResolveError::ReadFailed(_, reason) if reason == "permission denied"));
     }
 }
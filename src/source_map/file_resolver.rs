@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+///Abstracts over how [super::SourceMap] discovers directory entries and reads file contents, so
+///the deferred-load pipeline isn't hard-wired to the real filesystem. This is the seam tests use
+///to build a [SourceMap] from in-memory fixtures via [MockResolver], and the one the planned
+///plugin system (see the [super::SourceMap::source_tree] doc) would use to override the project
+///structure entirely.
+pub trait FileResolver{
+    ///Lists the immediate children of `path`.
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf>;
+
+    ///Whether `path` exists at all, as either a file or a directory.
+    fn exists(&self, path: &Path) -> bool;
+
+    ///Whether `path` is a directory rather than a file.
+    fn is_dir(&self, path: &Path) -> bool;
+
+    ///Reads the full contents of the file at `path`.
+    fn read_file(&self, path: &Path) -> std::result::Result<String, String>;
+}
+
+///Trait objects don't get [std::fmt::Debug] for free just because every implementor does, so
+///[super::deferred::DeferredSourceCode] (which holds an `Arc<dyn FileResolver>` and derives
+///[Debug]) needs this manual impl to compile.
+impl std::fmt::Debug for dyn FileResolver{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<dyn FileResolver>")
+    }
+}
+
+///The default [FileResolver], backed directly by [std::fs].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FsResolver;
+
+impl FileResolver for FsResolver{
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf>{
+        std::fs::read_dir(path)
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+            .unwrap_or_default()
+    }
+
+    fn exists(&self, path: &Path) -> bool{
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool{
+        path.is_dir()
+    }
+
+    fn read_file(&self, path: &Path) -> std::result::Result<String, String>{
+        std::fs::read_to_string(path).map_err(|err| format!("{0}: {1}", path.display(), err))
+    }
+}
+
+///An in-memory [FileResolver] mapping virtual paths directly to source strings, the way
+///rust-analyzer's `ra_db` mock resolves test fixtures without touching disk. A path is treated
+///as a directory if it isn't itself a registered file but prefixes one.
+#[derive(Clone, Debug, Default)]
+pub struct MockResolver{
+    files: HashMap<PathBuf, String>
+}
+
+impl MockResolver{
+    pub fn new() -> Self{
+        Self::default()
+    }
+
+    ///Registers `contents` under `path`, making it discoverable through [FileResolver::read_dir]
+    ///of its parent directory and readable through [FileResolver::read_file].
+    pub fn with_file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self{
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+}
+
+impl FileResolver for MockResolver{
+    fn read_dir(&self, path: &Path) -> Vec<PathBuf>{
+        // Lists both files directly under `path` and the implicit directories that prefix deeper
+        // files, taking the first component below `path` of every registered file.
+        let mut children: Vec<PathBuf> = self.files.keys()
+            .filter_map(|file_path| {
+                let first = file_path.strip_prefix(path).ok()?.components().next()?;
+                Some(path.join(first))
+            })
+            .collect();
+        children.sort();
+        children.dedup();
+        children
+    }
+
+    fn exists(&self, path: &Path) -> bool{
+        self.files.contains_key(path) || self.files.keys().any(|file_path| file_path.starts_with(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool{
+        !self.files.contains_key(path)
+            && self.files.keys().any(|file_path| file_path != path && file_path.starts_with(path))
+    }
+
+    fn read_file(&self, path: &Path) -> std::result::Result<String, String>{
+        self.files.get(path)
+            .cloned()
+            .ok_or_else(|| format!("{0}: no such mock file", path.display()))
+    }
+}
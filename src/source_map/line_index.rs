@@ -0,0 +1,71 @@
+use super::{source_file::LineColumn, BytePos};
+
+///Converts between a byte offset and a [LineColumn] within a single file's text, built once per
+///[super::source_file::SourceFile] the way rust-analyzer's `LineIndex` is built once per file: on
+///construction it scans the text a single time and records the byte offset of the start of every
+///line, so later lookups only need a binary search instead of a rescan.
+///
+///Offsets here are relative to the file's own text, not the [super::SourceMap]'s absolute
+///[BytePos] coordinate system; callers that hold an absolute position (such as
+///[super::source_file::SourceCode]) are responsible for subtracting the file's start first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineIndex{
+    ///The byte offset of the start of every line, relative to the file's text. The first entry
+    ///is always 0.
+    newlines: Vec<BytePos>,
+}
+
+impl LineIndex{
+    ///Scans `text` once, recording offset 0 and then the offset immediately after every `\n`.
+    pub fn new(text: &str) -> Self{
+        let mut newlines = vec![0];
+        newlines.extend(
+            text.char_indices()
+                .filter(|(_, c)| *c == '\n')
+                .map(|(idx, _)| idx + 1)
+        );
+        Self{ newlines }
+    }
+
+    ///Resolves a text-relative byte offset to its [LineColumn] by binary searching for the
+    ///greatest line-start `<= pos`, then counting Unicode scalar values from that line's start up
+    ///to `pos` so multibyte characters still report the column a human would expect.
+    ///
+    ///Panics if `pos` is out of bounds for `text`; callers are expected to have already validated
+    ///the position (e.g. [super::source_file::SourceCode::lookup_line_col] checks it against the
+    ///file's absolute range first).
+    pub fn line_col(&self, text: &str, pos: BytePos) -> LineColumn{
+        let line = self.newlines.partition_point(|start| *start <= pos) - 1;
+        let line_start = self.newlines[line];
+        let col = text[line_start..pos].chars().count();
+        LineColumn{ line, col }
+    }
+
+    ///The inverse of [Self::line_col]: turns a [LineColumn] back into a text-relative byte
+    ///offset, walking `line_col.col` Unicode scalar values into the line to account for multibyte
+    ///characters. Returns `None` if the line or column don't exist in `text`.
+    pub fn offset(&self, text: &str, line_col: LineColumn) -> Option<BytePos>{
+        let line_start = *self.newlines.get(line_col.line)?;
+        let line_end = self.line_end(text, line_col.line);
+        let line_text = text.get(line_start..line_end)?;
+
+        if line_col.col > line_text.chars().count(){
+            return None;
+        }
+
+        let consumed: usize = line_text.chars().take(line_col.col).map(char::len_utf8).sum();
+        Some(line_start + consumed)
+    }
+
+    ///The text-relative `[start, end)` byte range of a single line, without its trailing newline.
+    pub fn line_range(&self, text: &str, line: usize) -> Option<(BytePos, BytePos)>{
+        let line_start = *self.newlines.get(line)?;
+        Some((line_start, self.line_end(text, line)))
+    }
+
+    fn line_end(&self, text: &str, line: usize) -> BytePos{
+        self.newlines.get(line + 1)
+            .map(|next_start| next_start - 1)
+            .unwrap_or(text.len())
+    }
+}
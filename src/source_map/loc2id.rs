@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use super::{module_path::ModulePath, SourceFileID};
+
+///A bidirectional interning table from [ModulePath] to [SourceFileID], the way rust-analyzer's
+///`loc2id` assigns a dense id to each distinct location the first time it's seen and returns the
+///same id on every later lookup, rather than deriving the id by hashing part of the location.
+///
+///Backed by a `HashMap` for the forward direction and a `Vec` for the reverse, so [SourceFileID]
+///stays a small dense index suitable for arena-style storage instead of a hash that could
+///collide across distinct [ModulePath]s.
+#[derive(Clone, Debug, Default)]
+pub struct Loc2Id{
+    ids: HashMap<ModulePath, SourceFileID>,
+    paths: Vec<ModulePath>
+}
+
+impl Loc2Id{
+    pub fn new() -> Self{
+        Self::default()
+    }
+
+    ///Returns the existing [SourceFileID] for `path` if one was already interned, otherwise
+    ///assigns it the next monotonically increasing id and returns that.
+    pub fn intern(&mut self, path: ModulePath) -> SourceFileID{
+        if let Some(id) = self.ids.get(&path){
+            return id.clone();
+        }
+
+        let id = SourceFileID(self.paths.len() as u32);
+        self.paths.push(path.clone());
+        self.ids.insert(path, id.clone());
+        id
+    }
+
+    ///The inverse of [Self::intern]: resolves a previously interned [SourceFileID] back to its
+    ///[ModulePath].
+    pub fn lookup(&self, id: &SourceFileID) -> Option<&ModulePath>{
+        self.paths.get(id.0 as usize)
+    }
+}
@@ -1,21 +1,53 @@
-use std::{sync::Arc, collections::HashMap, path::Path, hash::{Hash, Hasher}};
+use std::{sync::Arc, rc::Rc, collections::HashMap, path::Path, cell::{Cell, RefCell}};
 
+use crate::span::Span;
 use crate::tree::{Tree, walk::TreeWalk};
 
-use self::{deferred::DeferredSourceFile, source_file::SourceFile};
+use self::{deferred::DeferredSourceFile, module_path::ModulePath, source_file::{LineColumn, SourceCode, SourceFile}};
 
 pub mod deferred;
+pub mod file_resolver;
+pub mod line_index;
+pub mod loc2id;
 pub mod module_path;
 pub mod source_file;
 
+use self::file_resolver::{FileResolver, FsResolver};
+use self::loc2id::Loc2Id;
+
 pub type BytePos = usize;
 
-///This represents an the id of a source file, which currently is very simple but when we get
-///incremental compilation, we can update this to rely on compilation sessions
-///
-///This is just a hash64 of the source file's project relative path
+thread_local! {
+    ///A thread-local [SourceMap] that isolated inputs (such as a [crate::lexer::Lexer] built
+    ///directly from a `&str` rather than a file on disk) register themselves into, the same way
+    ///proc-macro2's fallback lexer registers each parsed string as a dummy file. This lets a
+    ///[crate::span::Span] emitted from such an input still resolve back to a file, line/column,
+    ///and snippet through [SourceMap::describe].
+    pub static SOURCE_MAP: RefCell<SourceMap> = RefCell::new(SourceMap::empty());
+
+    ///A monotonically increasing counter used to name anonymous sources registered through
+    ///[SourceMap::register_source] when no real file path is available.
+    static NEXT_ANONYMOUS_ID: Cell<usize> = Cell::new(0);
+}
+
+///Registers `source_code` into the thread-local [SOURCE_MAP], returning the start [BytePos] that
+///was assigned to it. This is the hook a [crate::lexer::Lexer] built from a raw `&str` uses to
+///get its tokens' spans into the shared absolute coordinate system.
+pub fn register_anonymous_source(source_code: &str) -> BytePos{
+    let id = NEXT_ANONYMOUS_ID.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    SOURCE_MAP.with(|map| map.borrow_mut().register_source(format!("<input-{id}>"), source_code.into()))
+}
+
+///The id of a source file: a dense index assigned by [Loc2Id] the first time its [ModulePath] is
+///interned, rather than a hash of the path. This makes collisions across distinct paths
+///impossible and keeps ids suitable for arena-style storage, laying the groundwork for the
+///compilation-session-based incremental model this will eventually move to.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct SourceFileID(u64);
+pub struct SourceFileID(u32);
 
 ///A map of the project's entire source code, which allows for both tree walking and fetching the
 ///files themselves based on a distributed source file ID
@@ -44,66 +76,196 @@ pub struct SourceMap{
     pub source_tree: Arc<Tree<deferred::DeferredSourceFile>>,
     ///A map of ids to source files. This is used for fetching a source file that has already been
     ///referenced.
-    source_id_map: HashMap<SourceFileID, source_file::SourceFile>
+    source_id_map: HashMap<SourceFileID, source_file::SourceFile>,
+    ///The order in which files were loaded into [Self::source_id_map], which is also the order of
+    ///their start [BytePos] since each newly loaded file is appended to the running total. Kept
+    ///separately because a [HashMap] does not preserve insertion order, and [Self::lookup_file]
+    ///needs to binary search over file starts.
+    file_order: Vec<SourceFileID>,
+    ///Assigns each distinct [ModulePath] a stable, dense [SourceFileID] the first time it's
+    ///loaded. See [Loc2Id].
+    loc2id: Loc2Id,
+    ///How [Self::load_tree] discovers directory entries and [source_file::SourceFile] reads file
+    ///contents, rather than going straight to [std::fs]. See [FileResolver].
+    resolver: Arc<dyn FileResolver>
 }
 
 impl SourceMap{
-    fn load_tree(root_path: impl AsRef<Path>, parent: Option<module_path::ModulePath>) -> std::result::Result<Tree<deferred::DeferredSourceFile>, &'static str>{
+    fn load_tree(root_path: impl AsRef<Path>, parent: Option<module_path::ModulePath>, resolver: &Arc<dyn FileResolver>) -> std::result::Result<Tree<deferred::DeferredSourceFile>, &'static str>{
         let root_path = root_path.as_ref();
-        if !root_path.exists(){
+        if !resolver.exists(root_path){
             return Err("Root path does not exist!");
         }
-        if !root_path.is_dir(){
+        if !resolver.is_dir(root_path){
             return Err("Root path should be a directory");
         }
 
         let modpath = module_path::ModulePath::new(root_path, parent);
         let mut children = vec![];
-        for entry in std::fs::read_dir(root_path).unwrap(){
-            let entry = entry.unwrap().path();
-            let child_node = if entry.is_dir(){
-                Self::load_tree(entry, Some(modpath.clone()))?
+        for entry in resolver.read_dir(root_path){
+            let child_node = if resolver.is_dir(&entry){
+                Self::load_tree(entry, Some(modpath.clone()), resolver)?
             }else{
                 let modpath = module_path::ModulePath::new(entry, Some(modpath.clone()));
-                let deferred_sf = DeferredSourceFile::new(modpath);
+                let deferred_sf = DeferredSourceFile::new(modpath, resolver.clone());
                 Tree::Leaf(deferred_sf)
             };
             children.push(child_node);
         }
-        Ok(Tree::Branch(DeferredSourceFile::new(modpath), children))
+        Ok(Tree::Branch(DeferredSourceFile::new(modpath, resolver.clone()), children))
     }
 
+    ///Builds a [SourceMap] by walking `root_path` on the real filesystem. Equivalent to
+    ///[Self::with_resolver] with a [FsResolver].
     pub fn new(root_path: impl AsRef<Path>) -> std::result::Result<Self, &'static str>{
-        let tree = Self::load_tree(root_path, None)?;
+        Self::with_resolver(root_path, Arc::new(FsResolver))
+    }
+
+    ///Builds a [SourceMap] by walking `root_path` through `resolver` instead of going straight to
+    ///[std::fs], so tests and the planned plugin system can supply an in-memory project
+    ///structure (e.g. [file_resolver::MockResolver]) without touching disk.
+    pub fn with_resolver(root_path: impl AsRef<Path>, resolver: Arc<dyn FileResolver>) -> std::result::Result<Self, &'static str>{
+        let tree = Self::load_tree(root_path, None, &resolver)?;
         Ok(Self{
             source_tree: tree.into(),
-            source_id_map: HashMap::new()
+            source_id_map: HashMap::new(),
+            file_order: Vec::new(),
+            loc2id: Loc2Id::new(),
+            resolver
         })
     }
 
+    ///Constructs a [SourceMap] with no backing project directory at all, used by
+    ///[SOURCE_MAP] to hold sources that are registered directly (e.g. from [register_anonymous_source])
+    ///rather than discovered by walking a real source tree.
+    pub fn empty() -> Self{
+        let resolver: Arc<dyn FileResolver> = Arc::new(FsResolver);
+        let root = DeferredSourceFile::new(module_path::ModulePath::new("<anonymous>", None), resolver.clone());
+        Self{
+            source_tree: Tree::Leaf(root).into(),
+            source_id_map: HashMap::new(),
+            file_order: Vec::new(),
+            loc2id: Loc2Id::new(),
+            resolver
+        }
+    }
+
+    ///Registers `content` directly as a loaded [source_file::SourceFile] under `name`, bypassing
+    ///the deferred-load pipeline entirely. Used for sources that don't live on disk, such as a
+    ///[crate::lexer::Lexer] built from an arbitrary string. Returns the start [BytePos] assigned
+    ///to the new file.
+    pub fn register_source(&mut self, name: impl AsRef<Path>, content: Rc<str>) -> BytePos{
+        let module_path = ModulePath::new(name, None);
+        let offset = self.get_offset();
+        let source_code = SourceCode::new(offset, content);
+        let sf = SourceFile{ module_path, source_code: Some(source_code) };
+
+        let id = self.loc2id.intern(sf.module_path.clone());
+
+        self.source_id_map.insert(id.clone(), sf);
+        self.file_order.push(id);
+
+        offset
+    }
+
+    ///Resolves an absolute [BytePos] to its [LineColumn], locating the owning file via
+    ///[Self::lookup_file] and delegating to its [source_file::SourceCode]'s [line_index::LineIndex].
+    pub fn line_col(&self, pos: BytePos) -> std::result::Result<LineColumn, String>{
+        let file = self.lookup_file(pos)
+            .ok_or_else(|| format!("{0} byte pos is not within the bounds of any loaded file", pos))?;
+        let source_code = file.source_code.as_ref()
+            .ok_or_else(|| format!("{0} has no loaded source code", file.module_path))?;
+        source_code.lookup_line_col(pos)
+    }
+
+    ///The inverse of [Self::line_col]: turns a [LineColumn] within `module_path`'s file back into
+    ///an absolute [BytePos].
+    pub fn offset_of(&self, module_path: &module_path::ModulePath, line_col: LineColumn) -> std::result::Result<BytePos, String>{
+        let file = self.source_id_map.values().find(|file| &file.module_path == module_path)
+            .ok_or_else(|| format!("{0} is not a loaded file", module_path))?;
+        let source_code = file.source_code.as_ref()
+            .ok_or_else(|| format!("{0} has no loaded source code", file.module_path))?;
+        source_code.offset_of(line_col)
+    }
+
+    ///Describes `span` for a diagnostic in one call: the [ModulePath] of the file it belongs to,
+    ///its [LineColumn] within that file, and the source snippet it covers.
+    pub fn describe(&self, span: &Span) -> std::result::Result<(ModulePath, LineColumn, &str), String>{
+        let file = self.lookup_file(span.start_idx)
+            .ok_or_else(|| format!("{0} byte pos is not within the bounds of any loaded file", span.start_idx))?;
+        let source_code = file.source_code.as_ref()
+            .ok_or_else(|| format!("{0} has no loaded source code", file.module_path))?;
+        let line_col = source_code.lookup_line_col(span.start_idx)?;
+        let snippet = source_code.get_at_pos(span.start_idx, span.end_idx)?;
+
+        Ok((file.module_path.clone(), line_col, snippet))
+    }
+
+    ///The start [BytePos] that should be assigned to the next loaded [source_file::SourceFile],
+    ///equal to the running total of every previously loaded file's length plus a 1-byte separator
+    ///so that no two files' ranges overlap, the same way rustc's source map lays files end to end.
     fn get_offset(&self) -> usize{
-        if self.source_id_map.is_empty(){
-            0
-        }else{
-            self.source_id_map.iter()
-                .last()
-                .map(|(_, file)| file.get_offset())
-                .flatten()
-                .unwrap()
+        // Directories are loaded without source code and so have no end; skip back past them to
+        // the last file that does.
+        self.file_order.iter().rev()
+            .find_map(|id| self.source_id_map.get(id).and_then(|file| file.get_end()))
+            .map(|end| end + 1)
+            .unwrap_or(0)
+    }
+
+    ///Finds the loaded [source_file::SourceFile] whose absolute range contains `pos` by binary
+    ///searching over the file start offsets recorded in [Self::file_order], which stays sorted
+    ///since each file's start is always greater than the previous file's end.
+    pub fn lookup_file(&self, pos: BytePos) -> Option<&source_file::SourceFile>{
+        // Directories have no start offset, so they're left out rather than breaking the ordering.
+        let starts: Vec<(&SourceFileID, BytePos)> = self.file_order.iter()
+            .filter_map(|id| self.source_id_map.get(id).and_then(|file| file.get_offset()).map(|start| (id, start)))
+            .collect();
+
+        let idx = starts.partition_point(|(_, start)| *start <= pos);
+        if idx == 0{
+            return None;
         }
+
+        let id = starts[idx - 1].0;
+        self.source_id_map.get(id).filter(|file| file.source_contains_pos(pos))
     }
 
     pub fn get_file_with_pos(&mut self, pos: BytePos) -> Option<&source_file::SourceFile>{
-        self.source_id_map.iter().find(|(_, module)| module.source_contains_pos(pos)).map(|(_, module)| module)
+        self.lookup_file(pos)
+    }
+
+    ///Forces a [deferred::DeferredSourceFile] matching `module_path` in [Self::source_tree] to be
+    ///loaded into the map, returning the already-loaded [source_file::SourceFile] if one exists.
+    ///`optional` is forwarded to [deferred::DeferredSourceFile::resolve]: when true, a missing
+    ///backing file resolves to `Ok(None)` instead of [deferred::ResolveError].
+    pub fn load_module(&mut self, module_path: &module_path::ModulePath, optional: bool) -> std::result::Result<Option<source_file::SourceFile>, deferred::ResolveError>{
+        self.get_module(module_path.clone(), optional)
+    }
+
+    ///Finds the snippet of source code covered by `span`, locating the owning file via
+    ///[Self::lookup_file] and delegating the slicing to [source_file::SourceCode::get_at_pos].
+    pub fn span_to_snippet(&self, span: &Span) -> std::result::Result<&str, String>{
+        let file = self.lookup_file(span.start_idx)
+            .ok_or_else(|| format!("{0} byte pos is not within the bounds of any loaded file", span.start_idx))?;
+        let source_code = file.source_code.as_ref()
+            .ok_or_else(|| format!("{0} has no loaded source code", file.module_path))?;
+        source_code.get_at_pos(span.start_idx, span.end_idx)
     }
 
-    pub fn get_module(&mut self, module_path: module_path::ModulePath) -> Option<source_file::SourceFile>{
+    ///Finds the [deferred::DeferredSourceFile] matching `module_path` in [Self::source_tree] and
+    ///resolves it via [deferred::DeferredSourceFile::resolve], so that `optional` from-imports
+    ///(see [deferred::DeferredSourceFile::resolve]) can depend on a module that may legitimately
+    ///be absent without this call erroring out. Returns `Ok(None)` only when `module_path` isn't
+    ///in [Self::source_tree] at all, or when it is but `optional` is true and its backing file is
+    ///missing.
+    pub fn get_module(&mut self, module_path: module_path::ModulePath, optional: bool) -> std::result::Result<Option<source_file::SourceFile>, deferred::ResolveError>{
         if let Some((_, file)) = self.source_id_map.iter().find(|(_, file)| file.module_path == module_path){
-            return Some(file.clone())
+            return Ok(Some(file.clone()))
         }
 
         let mut walk: TreeWalk<DeferredSourceFile> = self.source_tree.into_iter();
-        let file = walk
+        let deferred_file = walk
             .find(|tree|{
                 match tree{
                     Tree::Leaf(file) => file.path == module_path,
@@ -113,15 +275,41 @@ impl SourceMap{
             .map(|tree| match tree{
                 Tree::Leaf(file) => file,
                 Tree::Branch(dir, _) => dir
-            })
-            .map(|module| SourceFile::new(module, self.get_offset()));
+            });
+
+        let deferred_file = match deferred_file{
+            None => return Ok(None),
+            Some(deferred_file) => deferred_file,
+        };
+
+        let file = deferred_file.resolve(self.get_offset(), optional)?;
         if let Some(ref sf) = file{
-            let mut hasher = std::collections::hash_map::DefaultHasher::new();
-            let name = sf.module_path.get_name();
-            name.hash(&mut hasher);
-            let hash = hasher.finish();
-            self.source_id_map.insert(SourceFileID(hash), sf.clone());
+            let id = self.loc2id.intern(sf.module_path.clone());
+            self.source_id_map.insert(id.clone(), sf.clone());
+            self.file_order.push(id);
         }
-        file
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use self::file_resolver::MockResolver;
+
+    #[test]
+    fn builds_source_map_from_mock_resolver() {
+        let resolver = MockResolver::new().with_file("root/a.sil", "fn main() {}");
+        let mut source_map = SourceMap::with_resolver("root", Arc::new(resolver))
+            .expect("mock project root should resolve");
+
+        let root = ModulePath::new("root", None);
+        let module_path = ModulePath::new("root/a.sil", Some(root));
+
+        let file = source_map.get_module(module_path, false)
+            .expect("module should resolve")
+            .expect("module should exist");
+        let source_code = file.source_code.expect("mock file should have loaded source code");
+        assert_eq!(source_code.content.as_ref(), "fn main() {}");
     }
 }
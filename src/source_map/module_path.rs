@@ -4,7 +4,7 @@ use std::rc::Rc;
 
 ///Represents a part of a whole project path, which is useful for recursing through the project and
 ///matching module symbols such as with processing and validating from-import statements
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ModulePath{
     ///The parent module if it exists at all or not, wrapped in an Rc instance for referencing
     ///throughout the compiler
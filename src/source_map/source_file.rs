@@ -1,7 +1,18 @@
 use std::path::Path;
 use std::rc::Rc;
 
-use super::{module_path::{self, ModulePath}, BytePos, deferred::DeferredSourceFile};
+use super::{module_path::{self, ModulePath}, line_index::LineIndex, BytePos, deferred::DeferredSourceFile};
+
+///A resolved human-readable position within a single source file, as opposed to the absolute
+///[BytePos] used for cross-file spans.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineColumn{
+    ///The 0-indexed line number within the file
+    pub line: usize,
+    ///The 0-indexed column, counted in Unicode scalar values rather than bytes so multibyte
+    ///characters still report the column a human would expect
+    pub col: usize,
+}
 
 ///This is simply a fat pointer to the contents of the source file, which is wrapped in an Rc to be
 ///referenced freely. This also contains the start of the file according to the absolute
@@ -13,10 +24,14 @@ pub struct SourceCode{
     start: BytePos,
     ///The end of the source content as an absolute position in the entire source map
     end: BytePos,
-    ///The source code itself wrapped in an Rc for distributing 
+    ///The source code itself wrapped in an Rc for distributing
     ///
     ///The source code here cannot be borrowed when nothing else owns it
-    pub content: Rc<str>
+    pub content: Rc<str>,
+    ///A [LineIndex] built once over [Self::content] at construction, so [Self::lookup_line_col]
+    ///and [Self::offset_of] can binary search instead of rescanning the whole file on every
+    ///lookup
+    line_index: LineIndex,
 }
 
 impl SourceCode{
@@ -24,9 +39,42 @@ impl SourceCode{
     ///in Rc
     pub fn new(start: BytePos, data: Rc<str>) -> Self{
         let end = data.len() + start;
+        let line_index = LineIndex::new(data.as_ref());
         Self{
-            start, end, content: data
+            start, end, content: data, line_index
+        }
+    }
+
+    ///Resolves an absolute [BytePos] to its [LineColumn] within this file, delegating the
+    ///relative-offset math to [Self::line_index].
+    pub fn lookup_line_col(&self, pos: BytePos) -> std::result::Result<LineColumn, String>{
+        if !(self.start..=self.end).contains(&pos){
+            return Err(format!("{0} byte pos is not within the bounds of this file", pos));
         }
+
+        Ok(self.line_index.line_col(self.content.as_ref(), pos - self.start))
+    }
+
+    ///The inverse of [Self::lookup_line_col]: turns a [LineColumn] back into an absolute
+    ///[BytePos] within this file.
+    pub fn offset_of(&self, line_col: LineColumn) -> std::result::Result<BytePos, String>{
+        self.line_index.offset(self.content.as_ref(), line_col)
+            .map(|rel_pos| rel_pos + self.start)
+            .ok_or_else(|| format!("{0:?} is not a valid position in this file", line_col))
+    }
+
+    ///Returns the text of a single 0-indexed line, without its trailing newline, for printing a
+    ///caret-style diagnostic under the offending source.
+    pub fn line_text(&self, line: usize) -> std::result::Result<&str, String>{
+        let (line_start, line_end) = self.line_index.line_range(self.content.as_ref(), line)
+            .ok_or_else(|| format!("{0} is not a valid line in this file", line))?;
+
+        Ok(self.content.as_ref()[line_start..line_end].trim_end_matches('\r'))
+    }
+
+    ///The end of the source content as an absolute position in the entire source map
+    pub fn end(&self) -> BytePos{
+        self.end
     }
 
     ///Attempts to get a slice of the internal data using start and end absolute positions, which
@@ -56,23 +104,25 @@ pub struct SourceFile{
 }
 
 impl SourceFile{
-    pub fn new(deferred: &DeferredSourceFile, offset: usize) -> Self{
+    ///Loads `deferred`'s backing file through its [DeferredSourceCode], assigning it `offset` as
+    ///its start [BytePos]. Returns `Err` with the underlying [super::file_resolver::FileResolver]
+    ///failure message if the path exists but its contents can't be read.
+    pub fn new(deferred: &DeferredSourceFile, offset: usize) -> std::result::Result<Self, String>{
         let path = deferred.path.clone();
         let path_str = path.to_string();
         let raw_path = Path::new(path_str.as_str());
-        let canon_path = std::env::current_dir().unwrap().join(raw_path);
-        if canon_path.is_dir(){
-            Self{
+        if !deferred.source_code.is_file(raw_path){
+            Ok(Self{
                 module_path: path,
                 source_code: None,
-            }
+            })
         }else{
-            let source_code_data = deferred.source_code.load(canon_path);
-            let source_code = SourceCode::new(offset, source_code_data.into());
-            Self{
+            let source_code_data = deferred.source_code.load(raw_path)?;
+            let source_code = SourceCode::new(offset, source_code_data);
+            Ok(Self{
                 module_path: path,
                 source_code: Some(source_code)
-            }
+            })
         }
     }
 
@@ -84,6 +134,12 @@ impl SourceFile{
         }
     }
 
+    ///The absolute end position of this file's source content, used by [super::SourceMap] to
+    ///compute the start offset of the next file loaded into the map
+    pub fn get_end(&self) -> Option<BytePos>{
+        self.source_code.as_ref().map(|source_code| source_code.end())
+    }
+
     pub fn source_contains_pos(&self, pos: BytePos) -> bool{
         if let Some(source_code) = &self.source_code{
             (source_code.start..source_code.end).contains(&pos)
@@ -1,4 +1,5 @@
 /// Represents a region in a source code, useful for error reporting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Span {
     pub start_idx: usize,
     pub end_idx: usize,
@@ -13,4 +14,33 @@ impl Span {
             len: end_idx - start_idx,
         }
     }
+
+    /// A span with no real source location, used for synthesized AST nodes that weren't produced
+    /// directly from a token (e.g. desugared or compiler-inserted code).
+    pub fn dummy() -> Self {
+        Span::new(0, 0)
+    }
+
+    /// Whether this span is a [Span::dummy], i.e. has no real source location.
+    pub fn is_dummy(&self) -> bool {
+        self.start_idx == 0 && self.end_idx == 0
+    }
+
+    /// Produces the smallest span covering both `self` and `other`, used when combining the spans
+    /// of several tokens into the span of the larger construct they make up.
+    pub fn join(&self, other: &Span) -> Span {
+        let start_idx = self.start_idx.min(other.start_idx);
+        let end_idx = self.end_idx.max(other.end_idx);
+        Span::new(start_idx, end_idx)
+    }
+
+    /// Convenience alias for [Span::join], read as "this span extended to `other`".
+    pub fn to(&self, other: &Span) -> Span {
+        self.join(other)
+    }
+
+    /// Whether `pos` falls within this span's `[start_idx, end_idx)` range.
+    pub fn contains(&self, pos: usize) -> bool {
+        (self.start_idx..self.end_idx).contains(&pos)
+    }
 }